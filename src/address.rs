@@ -0,0 +1,176 @@
+use regex::Regex;
+
+use crate::state::BedState;
+
+/// A single `ed`-style address: something that resolves to a line number
+/// against a `BedState`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Address {
+    Line(usize),
+    Current,
+    Last,
+    Offset(isize),
+    Mark(char),
+    SearchFwd(String),
+    SearchBwd(String),
+}
+
+/// A pair of resolved line numbers, inclusive on both ends.
+#[derive(Debug, Clone, Copy)]
+pub struct Range {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Range {
+    pub fn single(line: usize) -> Range {
+        Range {
+            start: line,
+            end: line,
+        }
+    }
+}
+
+/// Resolves an `Address` to a concrete 1-indexed line number, or `None`
+/// if it doesn't make sense against the current buffer (e.g. an unset
+/// mark, an offset that runs off either end of the file, or a search with
+/// no match).
+pub fn resolve_address(state: &BedState, address: &Address) -> Option<usize> {
+    match address {
+        Address::Line(line) => {
+            let max = state.content.line_len().max(1);
+            (1..=max).contains(line).then_some(*line)
+        }
+        Address::Current => Some(state.current_line),
+        Address::Last => Some(state.content.line_len()),
+        Address::Offset(delta) => {
+            let line = state.current_line as isize + delta;
+            let max = state.content.line_len().max(1) as isize;
+            (1..=max).contains(&line).then_some(line as usize)
+        }
+        Address::Mark(name) => state.marks.get(name).copied(),
+        Address::SearchFwd(pattern) => {
+            let re = Regex::new(pattern).ok()?;
+            search(state, &re, true)
+        }
+        Address::SearchBwd(pattern) => {
+            let re = Regex::new(pattern).ok()?;
+            search(state, &re, false)
+        }
+    }
+}
+
+/// Scans forward (or backward) from the line after (before) the current
+/// line, wrapping around the buffer, and returns the first line number
+/// whose text matches `re`.
+fn search(state: &BedState, re: &Regex, forward: bool) -> Option<usize> {
+    let max = state.content.line_len();
+    if max == 0 {
+        return None;
+    }
+    for step in 1..=max {
+        let offset = if forward { step } else { max - step };
+        let line = (state.current_line - 1 + offset) % max;
+        if re.is_match(&state.content.line(line).to_string()) {
+            return Some(line + 1);
+        }
+    }
+    None
+}
+
+/// Parses a single address token from the front of `input`, returning the
+/// parsed `Address` together with the remainder of the string.
+pub fn parse_address(input: &str) -> Option<(Address, &str)> {
+    let mut chars = input.char_indices();
+    match chars.next() {
+        Some((_, c)) if c.is_ascii_digit() => {
+            let end = input
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(input.len());
+            let line: usize = input[..end].parse().ok()?;
+            Some((Address::Line(line), &input[end..]))
+        }
+        Some((_, '.')) => Some((Address::Current, &input[1..])),
+        Some((_, '$')) => Some((Address::Last, &input[1..])),
+        Some((_, '\'')) => {
+            let mark = input[1..].chars().next()?;
+            Some((Address::Mark(mark), &input[1 + mark.len_utf8()..]))
+        }
+        Some((_, '/')) => {
+            let rest = &input[1..];
+            let end = rest.find('/')?;
+            Some((Address::SearchFwd(rest[..end].to_string()), &rest[end + 1..]))
+        }
+        Some((_, '?')) => {
+            let rest = &input[1..];
+            let end = rest.find('?')?;
+            Some((Address::SearchBwd(rest[..end].to_string()), &rest[end + 1..]))
+        }
+        Some((_, sign @ ('+' | '-'))) => {
+            let rest = &input[1..];
+            let end = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let magnitude: isize = if end == 0 { 1 } else { rest[..end].parse().ok()? };
+            let offset = if sign == '-' { -magnitude } else { magnitude };
+            Some((Address::Offset(offset), &rest[end..]))
+        }
+        _ => None,
+    }
+}
+
+/// Parses an optional `addr,addr` or `addr;addr` range from the front of
+/// `input`, resolving each side against `state`. A bare single address is
+/// treated as both ends of the range. `;` additionally moves the current
+/// line to the left address before the right one is resolved, matching
+/// `ed`. An address that's missing defaults sensibly (`1` / the last
+/// line / the current line); an address that's present but doesn't
+/// resolve (an out-of-range line, an unset mark, a failed search, ...) is
+/// an error rather than a silent fallback, since defaulting it would turn
+/// a typo'd address into "run this over the whole buffer".
+pub fn parse_range<'a>(input: &'a str, state: &mut BedState) -> (Result<Option<Range>, ()>, &'a str) {
+    let (first, rest) = match parse_address(input) {
+        Some((addr, rest)) => (Some(addr), rest),
+        None => (None, input),
+    };
+
+    match rest.chars().next() {
+        Some(sep @ (',' | ';')) => {
+            let rest = &rest[1..];
+
+            let start = match &first {
+                Some(addr) => match resolve_address(state, addr) {
+                    Some(line) => line,
+                    None => return (Err(()), rest),
+                },
+                None => 1,
+            };
+
+            if sep == ';' {
+                state.current_line = start;
+            }
+
+            let (second, rest) = match parse_address(rest) {
+                Some((addr, rest)) => (Some(addr), rest),
+                None => (None, rest),
+            };
+
+            let end = match &second {
+                Some(addr) => match resolve_address(state, addr) {
+                    Some(line) => line,
+                    None => return (Err(()), rest),
+                },
+                None => state.content.line_len(),
+            };
+
+            (Ok(Some(Range { start, end })), rest)
+        }
+        _ => match first {
+            Some(addr) => match resolve_address(state, &addr) {
+                Some(line) => (Ok(Some(Range::single(line))), rest),
+                None => (Err(()), rest),
+            },
+            None => (Ok(None), rest),
+        },
+    }
+}
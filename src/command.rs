@@ -0,0 +1,198 @@
+use crate::address::{parse_range, Range};
+use crate::state::BedState;
+
+#[derive(Debug)]
+pub enum BedCommand {
+    Quit,
+    Print { range: Range },
+    NPrint { range: Range },
+    Move { line: usize },
+    Change,
+    Write { filename: Option<String> },
+    Edit { filename: String },
+    SetMark { name: char, line: usize },
+    Undo,
+    Redo,
+    Substitute {
+        range: Range,
+        pattern: String,
+        replacement: String,
+        global: bool,
+    },
+    Filter {
+        range: Range,
+        command: String,
+    },
+    Global {
+        range: Range,
+        pattern: String,
+        invert: bool,
+        command: String,
+    },
+    None,
+}
+
+/// Parses the body of an `s` command: `s<delim>pattern<delim>replacement<delim>[g]`.
+/// Any non-alphanumeric character may act as the delimiter (not just `/`),
+/// and the trailing delimiter before the flags is optional.
+fn parse_substitute(rest: &str) -> Option<(String, String, bool)> {
+    let mut chars = rest.chars();
+    if chars.next()? != 's' {
+        return None;
+    }
+    let delim = chars.next()?;
+    if delim.is_alphanumeric() {
+        return None;
+    }
+
+    let body = &rest[1 + delim.len_utf8()..];
+    let pattern_end = body.find(delim)?;
+    let pattern = &body[..pattern_end];
+    let after_pattern = &body[pattern_end + delim.len_utf8()..];
+
+    let (replacement, global) = match after_pattern.find(delim) {
+        Some(replacement_end) => {
+            let replacement = &after_pattern[..replacement_end];
+            let flags = &after_pattern[replacement_end + delim.len_utf8()..];
+            (replacement, flags.contains('g'))
+        }
+        None => (after_pattern, false),
+    };
+
+    Some((pattern.to_string(), replacement.to_string(), global))
+}
+
+/// Parses the body of a `g`/`v` command: `g<delim>pattern<delim>cmd` runs
+/// `cmd` on every line matching the pattern, `v<delim>pattern<delim>cmd`
+/// on every line that doesn't.
+fn parse_global(rest: &str) -> Option<(String, bool, String)> {
+    let mut chars = rest.chars();
+    let invert = match chars.next()? {
+        'g' => false,
+        'v' => true,
+        _ => return None,
+    };
+    let delim = chars.next()?;
+    if delim.is_alphanumeric() {
+        return None;
+    }
+
+    let body = &rest[1 + delim.len_utf8()..];
+    let pattern_end = body.find(delim)?;
+    let pattern = &body[..pattern_end];
+    let command = &body[pattern_end + delim.len_utf8()..];
+
+    Some((pattern.to_string(), invert, command.to_string()))
+}
+
+/// Parses one REPL line into a `BedCommand`. An input is an optional
+/// address or range (see `address::parse_range`) followed by a command
+/// letter; a bare address with no command letter moves to that line.
+pub fn parse_command(input: &str, state: &mut BedState) -> BedCommand {
+    let input = input.trim();
+    let (range, rest) = parse_range(input, state);
+    let range = match range {
+        Ok(range) => range,
+        Err(()) => {
+            eprintln!("Invalid address");
+            return BedCommand::None;
+        }
+    };
+    let rest = rest.trim();
+
+    if rest.is_empty() {
+        return match range {
+            Some(range) => BedCommand::Move { line: range.end },
+            None => BedCommand::None,
+        };
+    }
+
+    match rest {
+        "q" | "quit" => BedCommand::Quit,
+        "w" => BedCommand::Write { filename: None },
+        "u" => BedCommand::Undo,
+        "redo" => BedCommand::Redo,
+        "c" => {
+            if let Some(range) = range {
+                state.current_line = range.end;
+            }
+            BedCommand::Change
+        }
+        "p" => BedCommand::Print {
+            range: range.unwrap_or(Range::single(state.current_line)),
+        },
+        "n" => BedCommand::NPrint {
+            range: range.unwrap_or(Range::single(state.current_line)),
+        },
+        _ if rest.starts_with('k') && rest.chars().count() == 2 => {
+            let name = rest.chars().nth(1).unwrap();
+            let line = range.map(|r| r.end).unwrap_or(state.current_line);
+            BedCommand::SetMark { name, line }
+        }
+        _ if rest.starts_with('s') => match parse_substitute(rest) {
+            Some((pattern, replacement, global)) => BedCommand::Substitute {
+                range: range.unwrap_or(Range::single(state.current_line)),
+                pattern,
+                replacement,
+                global,
+            },
+            None => {
+                eprintln!("Unknown command: {}", input);
+                BedCommand::None
+            }
+        },
+        _ if rest.starts_with('g') || rest.starts_with('v') => match parse_global(rest) {
+            Some((pattern, invert, command)) => BedCommand::Global {
+                range: range.unwrap_or(Range {
+                    start: 1,
+                    end: state.content.line_len(),
+                }),
+                pattern,
+                invert,
+                command,
+            },
+            None => {
+                eprintln!("Unknown command: {}", input);
+                BedCommand::None
+            }
+        },
+        _ if rest.starts_with("w ") => {
+            let filename = rest[2..].trim();
+            if filename.is_empty() {
+                eprintln!("Unknown command: {}", input);
+                BedCommand::None
+            } else {
+                BedCommand::Write {
+                    filename: Some(filename.to_string()),
+                }
+            }
+        }
+        _ if rest.starts_with("e ") => {
+            let filename = rest[2..].trim();
+            if filename.is_empty() {
+                eprintln!("Unknown command: {}", input);
+                BedCommand::None
+            } else {
+                BedCommand::Edit {
+                    filename: filename.to_string(),
+                }
+            }
+        }
+        _ if rest.starts_with('!') => {
+            let command = rest[1..].trim().to_string();
+            if command.is_empty() {
+                eprintln!("Unknown command: {}", input);
+                BedCommand::None
+            } else {
+                BedCommand::Filter {
+                    range: range.unwrap_or(Range::single(state.current_line)),
+                    command,
+                }
+            }
+        }
+        _ => {
+            eprintln!("Unknown command: {}", input);
+            BedCommand::None
+        }
+    }
+}
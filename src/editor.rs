@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use libc::{termios, ECHO, ICANON, ICRNL, IXON, OPOST, TCSANOW, VMIN, VTIME};
+
+/// A small rustyline-style line editor: raw-mode input, persistent
+/// history, in-line cursor editing, and Tab completion for command
+/// letters and filenames.
+pub struct Editor {
+    history: Vec<String>,
+    history_path: PathBuf,
+    commands: HashSet<char>,
+}
+
+/// Puts the terminal into raw mode for the lifetime of the guard, and
+/// restores the original settings on drop.
+struct RawMode {
+    original: termios,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        unsafe {
+            let mut original: termios = std::mem::zeroed();
+            if libc::tcgetattr(0, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            raw.c_lflag &= !(ECHO | ICANON);
+            raw.c_iflag &= !(IXON | ICRNL);
+            raw.c_oflag &= !OPOST;
+            raw.c_cc[VMIN] = 1;
+            raw.c_cc[VTIME] = 0;
+            if libc::tcsetattr(0, TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(RawMode { original })
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(0, TCSANOW, &self.original);
+        }
+    }
+}
+
+impl Editor {
+    pub fn new(history_path: PathBuf, commands: &[char]) -> Self {
+        let history = fs::read_to_string(&history_path)
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        Editor {
+            history,
+            history_path,
+            commands: commands.iter().copied().collect(),
+        }
+    }
+
+    /// Reads one line from the terminal with history recall (Up/Down),
+    /// Ctrl-A/E/K/U editing, and Tab completion. Returns `None` on EOF
+    /// (Ctrl-D on an empty line).
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let _raw = RawMode::enable()?;
+        let mut stdout = io::stdout();
+
+        let mut buf: Vec<char> = Vec::new();
+        let mut cursor = 0usize;
+        let mut history_index = self.history.len();
+
+        write!(stdout, "{}", prompt)?;
+        stdout.flush()?;
+
+        loop {
+            let mut byte = [0u8; 1];
+            if io::stdin().read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+
+            match byte[0] {
+                b'\r' | b'\n' => {
+                    write!(stdout, "\r\n")?;
+                    break;
+                }
+                0x04 if buf.is_empty() => return Ok(None), // Ctrl-D
+                0x01 => cursor = 0,                        // Ctrl-A: start of line
+                0x05 => cursor = buf.len(),                 // Ctrl-E: end of line
+                0x0b => buf.truncate(cursor),                // Ctrl-K: kill to end of line
+                0x15 => {
+                    // Ctrl-U: kill back to the start of the line
+                    buf.drain(0..cursor);
+                    cursor = 0;
+                }
+                0x7f | 0x08 if cursor > 0 => {
+                    // Backspace
+                    cursor -= 1;
+                    buf.remove(cursor);
+                }
+                0x7f | 0x08 => {}
+                b'\t' => self.complete(&mut buf, &mut cursor),
+                0x1b => {
+                    // Escape sequence: only arrow keys (`\x1b[A/B/C/D`) are handled
+                    let mut seq = [0u8; 2];
+                    io::stdin().read_exact(&mut seq)?;
+                    if seq[0] == b'[' {
+                        match seq[1] {
+                            b'A' if history_index > 0 => {
+                                // Up: recall older history
+                                history_index -= 1;
+                                buf = self.history[history_index].chars().collect();
+                                cursor = buf.len();
+                            }
+                            b'A' => {}
+                            b'B' => {
+                                // Down: recall newer history
+                                if history_index + 1 < self.history.len() {
+                                    history_index += 1;
+                                    buf = self.history[history_index].chars().collect();
+                                } else {
+                                    history_index = self.history.len();
+                                    buf.clear();
+                                }
+                                cursor = buf.len();
+                            }
+                            b'C' if cursor < buf.len() => cursor += 1, // Right
+                            b'D' if cursor > 0 => cursor -= 1,         // Left
+                            _ => {}
+                        }
+                    }
+                }
+                c if c.is_ascii_graphic() || c == b' ' => {
+                    buf.insert(cursor, c as char);
+                    cursor += 1;
+                }
+                _ => {}
+            }
+
+            self.redraw(&mut stdout, prompt, &buf, cursor)?;
+        }
+
+        let line: String = buf.into_iter().collect();
+        if !line.trim().is_empty() {
+            self.history.push(line.clone());
+        }
+        Ok(Some(line))
+    }
+
+    /// Repaints the prompt and buffer in place, then walks the cursor
+    /// back to its logical position with `\x1b[nD` (the `output.back(n)`
+    /// pattern).
+    fn redraw(
+        &self,
+        stdout: &mut io::Stdout,
+        prompt: &str,
+        buf: &[char],
+        cursor: usize,
+    ) -> io::Result<()> {
+        let line: String = buf.iter().collect();
+        write!(stdout, "\r\x1b[K{}{}", prompt, line)?;
+        let back = buf.len() - cursor;
+        if back > 0 {
+            write!(stdout, "\x1b[{}D", back)?;
+        }
+        stdout.flush()
+    }
+
+    /// Tab completion: command letters when the text before the cursor
+    /// looks like a bare command, filenames for the trailing argument
+    /// otherwise (e.g. after `w ` or `e `).
+    fn complete(&self, buf: &mut Vec<char>, cursor: &mut usize) {
+        let prefix: String = buf[..*cursor].iter().collect();
+
+        let candidates: Vec<String> = match prefix.rfind(' ') {
+            Some(idx) => {
+                let arg_prefix = &prefix[idx + 1..];
+                complete_filename(arg_prefix)
+                    .into_iter()
+                    .map(|name| format!("{}{}", &prefix[..idx + 1], name))
+                    .collect()
+            }
+            None if !prefix.is_empty() && prefix.chars().all(char::is_alphabetic) => self
+                .commands
+                .iter()
+                .map(|c| c.to_string())
+                .filter(|candidate| candidate.starts_with(&prefix))
+                .collect(),
+            None => complete_filename(&prefix),
+        };
+
+        if let [only] = candidates.as_slice() {
+            for c in only[prefix.len()..].chars() {
+                buf.insert(*cursor, c);
+                *cursor += 1;
+            }
+        }
+    }
+
+    /// Persists the history ring to the dotfile so it survives restarts.
+    pub fn save_history(&self) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.history_path)?;
+        for line in &self.history {
+            writeln!(file, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Completes the last path segment of `prefix` against the directory
+/// that contains it, like rustyline's `FilenameCompleter`.
+fn complete_filename(prefix: &str) -> Vec<String> {
+    let path = Path::new(prefix);
+    let (dir, partial) = match (path.parent(), path.file_name()) {
+        (Some(dir), Some(name)) if !dir.as_os_str().is_empty() => {
+            (dir.to_path_buf(), name.to_string_lossy().to_string())
+        }
+        _ => (PathBuf::from("."), prefix.to_string()),
+    };
+
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(&partial))
+        .map(|name| {
+            if dir == Path::new(".") {
+                name
+            } else {
+                dir.join(name).to_string_lossy().to_string()
+            }
+        })
+        .collect()
+}
@@ -1,98 +1,21 @@
+//! bed - a basic editor like ed but with a modern interface
+//! Author: Luc Videau
+
 use crop::Rope;
 use regex::Regex;
 use std::string::ToString;
 use std::{env, io::Write};
 
-/**
- * bed - a basic editor like ed but with a modern interface
- * Author: Luc Videau
- */
-
-#[derive(Debug)]
-struct Range {
-    start: usize,
-    end: usize,
-}
-
-enum BedCommand {
-    Quit,
-    Print { range: Range },
-    NPrint { range: Range },
-    Move { line: usize },
-    Change,
-    Write,
-    None,
-}
-
-struct BedState {
-    content: Rope,
-    current_line: usize,
-}
+mod address;
+mod command;
+mod editor;
+mod state;
+mod undo;
 
-fn parse_command(input: &str, current_line: usize, max_line: usize) -> BedCommand {
-    let input = input.trim();
-
-    /* Regular Expressions */
-    let quit_re = Regex::new(r"^(q|quit)$").unwrap();
-    let print_re = Regex::new(r"^(\d+)?,?(\s)?(\d+)?(\s)?[pn]$").unwrap();
-    let move_re = Regex::new(r"^(\d+)$").unwrap();
-    let change_re = Regex::new(r"^c\s*$").unwrap();
-    let write_re = Regex::new(r"^w\s*$").unwrap();
-
-    /* Match the input with the regular expressions */
-    if quit_re.is_match(input) {
-        BedCommand::Quit
-    } else if print_re.is_match(input) {
-        // start, end p  => print the lines from start to end
-        let captures = print_re.captures(input).unwrap();
-
-        let start = captures.get(1).map(|m| m.as_str().parse().unwrap());
-        let end = captures.get(3).map(|m| m.as_str().parse().unwrap());
-
-        let range = match input.contains(",") {
-            true => {
-                // if the second capture group is Some ','
-                Range {
-                    start: start.unwrap_or_else(|| 1),
-                    end: end.unwrap_or_else(|| max_line),
-                }
-            }
-            false => {
-                // if the second capture group is None
-                Range {
-                    start: start.unwrap_or_else(|| current_line),
-                    end: start.unwrap_or_else(|| current_line),
-                }
-            }
-        };
-
-        if input.ends_with("p") {
-            BedCommand::Print { range }
-        } else if input.ends_with("n") {
-            BedCommand::NPrint { range }
-        } else {
-            eprintln!("Unknown command: {}", input);
-            BedCommand::None
-        }
-    } else if change_re.is_match(input) {
-        BedCommand::Change
-    } else if move_re.is_match(input) {
-        let line = move_re
-            .captures(input)
-            .unwrap()
-            .get(1)
-            .unwrap()
-            .as_str()
-            .parse()
-            .unwrap();
-        BedCommand::Move { line }
-    } else if write_re.is_match(input) {
-        BedCommand::Write
-    } else {
-        eprintln!("Unknown command: {}", input);
-        BedCommand::None
-    }
-}
+use command::{parse_command, BedCommand};
+use editor::Editor;
+use state::BedState;
+use undo::{Changeset, Edit};
 
 fn main() {
     /*
@@ -113,72 +36,297 @@ fn main() {
     let file = std::fs::read_to_string(&args[1]).unwrap();
 
     // Create the initial state of the editor
-    let mut state = BedState {
-        content: Rope::from(file),
-        current_line: 1,
-    };
-    state.current_line = state.content.line_len();
+    let mut state = BedState::new(Rope::from(file), args[1].clone());
+
+    let history_path = env::var("HOME")
+        .map(|home| std::path::PathBuf::from(home).join(".bed_history"))
+        .unwrap_or_else(|_| std::path::PathBuf::from(".bed_history"));
+    let mut editor = Editor::new(
+        history_path,
+        &['q', 'w', 'e', 'u', 'c', 'p', 'n', 'k', 's', 'g', 'v'],
+    );
 
     // REPL loop
     loop {
-        // print the prompt
-        print!(":");
-        std::io::stdout().flush().unwrap();
-
         // wait for the user to enter a command
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap();
+        let input = match editor.read_line(":") {
+            Ok(Some(input)) => input,
+            Ok(None) => break,
+            Err(err) => {
+                eprintln!("Failed to read input: {}", err);
+                break;
+            }
+        };
 
         // execute the command
-        let command = parse_command(&input, state.current_line, state.content.line_len());
-        match command {
-            BedCommand::None => continue,
-            BedCommand::Quit => break,
-            BedCommand::Write => {
-                std::fs::write(&args[1], state.content.to_string()).unwrap();
+        let command = parse_command(&input, &mut state);
+        if execute(&mut state, command) {
+            break;
+        }
+    }
+
+    if let Err(err) = editor.save_history() {
+        eprintln!("Failed to save history: {}", err);
+    }
+}
+
+/// Runs one parsed command against `state`, returning `true` if it
+/// requested that the REPL quit. Also used recursively by `Global` to run
+/// its sub-command on each matching line.
+fn execute(state: &mut BedState, command: BedCommand) -> bool {
+    match command {
+        BedCommand::None => {}
+        BedCommand::Quit => return true,
+        BedCommand::Write { filename } => {
+            let target = filename.unwrap_or_else(|| state.filename.clone());
+            match std::fs::write(&target, state.content.to_string()) {
+                Ok(()) => state.filename = target,
+                Err(err) => eprintln!("Failed to write `{}`: {}", target, err),
             }
-            BedCommand::Change => {
-                // Get lines until regex ^.$ is matched
-                let end_re = Regex::new(r"^\.\n$").unwrap();
-                let mut new_content = String::new();
-                loop {
-                    let mut line = String::new();
-                    std::io::stdin().read_line(&mut line).unwrap();
-                    if end_re.is_match(&line) {
-                        break;
-                    }
-                    new_content.push_str(&line);
-                }
-                // remove the current line from the content
-                let byte_start = state.content.byte_of_line(state.current_line - 1);
-                let byte_width = state.content.line(state.current_line - 1).byte_len();
-                let byte_end = byte_start + byte_width;
-                state.content.delete(byte_start..byte_end);
-                // insert the new content at the current line
-                state.content.insert(byte_start, &new_content.trim_end());
+        }
+        BedCommand::Edit { filename } => match std::fs::read_to_string(&filename) {
+            Ok(text) => {
+                state.content = Rope::from(text);
+                state.current_line = state.content.line_len();
+                state.filename = filename;
+                state.marks.clear();
+                state.undo.clear();
+                state.redo.clear();
             }
-            BedCommand::Print { range } => {
-                for line in (range.start - 1)..range.end {
-                    let string = state.content.line(line).to_string();
-                    print!("{}\n", string);
+            Err(err) => eprintln!("Failed to read `{}`: {}", filename, err),
+        },
+        BedCommand::Change => {
+            // Get lines until regex ^.$ is matched
+            let end_re = Regex::new(r"^\.\n$").unwrap();
+            let mut new_content = String::new();
+            loop {
+                let mut line = String::new();
+                std::io::stdin().read_line(&mut line).unwrap();
+                if end_re.is_match(&line) {
+                    break;
                 }
-                print!("\x1b[0m");
+                new_content.push_str(&line);
+            }
+            // remove the current line from the content
+            let byte_start = state.content.byte_of_line(state.current_line - 1);
+            let byte_width = state.content.line(state.current_line - 1).byte_len();
+            let byte_end = byte_start + byte_width;
+            let old_text = state.content.line(state.current_line - 1).to_string();
+            let new_text = new_content.trim_end().to_string();
+            state.content.delete(byte_start..byte_end);
+            // insert the new content at the current line
+            state.content.insert(byte_start, &new_text);
+
+            let mut changeset = Changeset::new();
+            changeset.push(Edit::Delete {
+                byte: byte_start,
+                text: old_text,
+            });
+            changeset.push(Edit::Insert {
+                byte: byte_start,
+                text: new_text,
+            });
+            state.record(changeset);
+        }
+        BedCommand::Print { range } => {
+            for line in (range.start - 1)..range.end {
+                let string = state.content.line(line).to_string();
+                print!("{}\n", string);
             }
-            BedCommand::NPrint { range } => {
-                // get the width of the line number (the number of digits)
-                let width = state.content.line_len().to_string().len();
+            print!("\x1b[0m");
+        }
+        BedCommand::NPrint { range } => {
+            // get the width of the line number (the number of digits)
+            let width = state.content.line_len().to_string().len();
 
+            for line in (range.start - 1)..range.end {
+                let string = state.content.line(line).to_string();
+                // reset the color to the default color
+                print!("{:width$} â”‚ ", line + 1, width = width);
+                print!("{}\n", string);
+                print!("\x1b[0m");
+            }
+        }
+        BedCommand::Move { line } => {
+            state.current_line = line;
+        }
+        BedCommand::SetMark { name, line } => {
+            state.marks.insert(name, line);
+        }
+        BedCommand::Undo => match state.undo.pop() {
+            Some(changeset) => {
+                changeset.apply_inverse(&mut state.content);
+                state.redo.push(changeset);
+            }
+            None => eprintln!("Nothing to undo"),
+        },
+        BedCommand::Redo => match state.redo.pop() {
+            Some(changeset) => {
+                changeset.apply(&mut state.content);
+                state.undo.push(changeset);
+            }
+            None => eprintln!("Nothing to redo"),
+        },
+        BedCommand::Substitute {
+            range,
+            pattern,
+            replacement,
+            global,
+        } => match Regex::new(&pattern) {
+            Ok(re) => {
+                let mut changeset = Changeset::new();
                 for line in (range.start - 1)..range.end {
-                    let string = state.content.line(line).to_string();
-                    // reset the color to the default color
-                    print!("{:width$} â”‚ ", line + 1, width = width);
-                    print!("{}\n", string);
-                    print!("\x1b[0m");
+                    let text = state.content.line(line).to_string();
+                    let new_text = if global {
+                        re.replace_all(&text, replacement.as_str()).to_string()
+                    } else {
+                        re.replace(&text, replacement.as_str()).to_string()
+                    };
+                    if new_text != text {
+                        let byte_start = state.content.byte_of_line(line);
+                        let byte_width = state.content.line(line).byte_len();
+                        let byte_end = byte_start + byte_width;
+                        state.content.delete(byte_start..byte_end);
+                        state.content.insert(byte_start, &new_text);
+                        changeset.push(Edit::Delete {
+                            byte: byte_start,
+                            text,
+                        });
+                        changeset.push(Edit::Insert {
+                            byte: byte_start,
+                            text: new_text,
+                        });
+                        state.current_line = line + 1;
+                    }
                 }
+                state.record(changeset);
             }
-            BedCommand::Move { line } => {
-                state.current_line = line;
+            Err(err) => eprintln!("Invalid pattern: {}", err),
+        },
+        BedCommand::Filter { range, command } => {
+            use std::io::ErrorKind;
+            use std::process::{Command, Stdio};
+
+            let input_text: String = (range.start - 1..range.end)
+                .map(|line| state.content.line(line).to_string() + "\n")
+                .collect();
+
+            let child = Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn();
+
+            match child {
+                Ok(mut child) => {
+                    let mut stdin = child.stdin.take().unwrap();
+                    // Feed stdin from another thread: a filter that exits
+                    // before reading all of it (e.g. `head -n1`) closes its
+                    // end of the pipe, and that's not our error to report.
+                    let writer_input = input_text.clone();
+                    let writer = std::thread::spawn(move || {
+                        if let Err(err) = stdin.write_all(writer_input.as_bytes()) {
+                            if err.kind() != ErrorKind::BrokenPipe {
+                                eprintln!("Failed to write to filter stdin: {}", err);
+                            }
+                        }
+                    });
+
+                    match child.wait_with_output() {
+                        Ok(output) if output.status.success() => {
+                            let new_text =
+                                String::from_utf8_lossy(&output.stdout).trim_end().to_string();
+
+                            let byte_start = state.content.byte_of_line(range.start - 1);
+                            let byte_end = if range.end < state.content.line_len() {
+                                state.content.byte_of_line(range.end - 1)
+                                    + state.content.line(range.end - 1).byte_len()
+                            } else {
+                                // The range reaches EOF: include the last line's
+                                // own newline (if any) so it doesn't survive as
+                                // a spurious blank line.
+                                state.content.byte_len()
+                            };
+                            let old_text =
+                                state.content.byte_slice(byte_start..byte_end).to_string();
+
+                            state.content.delete(byte_start..byte_end);
+                            state.content.insert(byte_start, &new_text);
+
+                            let mut changeset = Changeset::new();
+                            changeset.push(Edit::Delete {
+                                byte: byte_start,
+                                text: old_text,
+                            });
+                            changeset.push(Edit::Insert {
+                                byte: byte_start,
+                                text: new_text,
+                            });
+                            state.record(changeset);
+                            state.current_line = range.start;
+                        }
+                        Ok(output) => eprintln!(
+                            "Command `{}` exited with {}: {}",
+                            command,
+                            output.status,
+                            String::from_utf8_lossy(&output.stderr).trim_end()
+                        ),
+                        Err(err) => eprintln!("Failed to run `{}`: {}", command, err),
+                    }
+
+                    let _ = writer.join();
+                }
+                Err(err) => eprintln!("Failed to run `{}`: {}", command, err),
             }
         }
+        BedCommand::Global {
+            range,
+            pattern,
+            invert,
+            command,
+        } => match Regex::new(&pattern) {
+            Ok(re) => {
+                // Resolve the matching lines to byte offsets up front: the
+                // sub-command can insert or delete text and shift every
+                // line number after it, but a byte offset still pins down
+                // the same line. A byte offset is only stable up to the
+                // point where a sub-command changes the buffer's length,
+                // though, so every offset after the one just processed is
+                // shifted by the resulting delta before it's used.
+                let mut marked_bytes: Vec<usize> = (range.start - 1..range.end)
+                    .filter(|&line| {
+                        let text = state.content.line(line).to_string();
+                        re.is_match(&text) != invert
+                    })
+                    .map(|line| state.content.byte_of_line(line))
+                    .collect();
+
+                let mut i = 0;
+                while i < marked_bytes.len() {
+                    let byte = marked_bytes[i];
+                    let line = state.content.line_of_byte(byte);
+                    state.current_line = line + 1;
+                    let before_len = state.content.byte_len();
+                    let sub_command = parse_command(&command, state);
+                    if execute(state, sub_command) {
+                        return true;
+                    }
+                    let delta = state.content.byte_len() as isize - before_len as isize;
+                    if delta != 0 {
+                        for later in &mut marked_bytes[i + 1..] {
+                            if *later > byte {
+                                *later = (*later as isize + delta) as usize;
+                            }
+                        }
+                    }
+                    i += 1;
+                }
+            }
+            Err(err) => eprintln!("Invalid pattern: {}", err),
+        },
     }
+
+    false
 }
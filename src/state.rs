@@ -0,0 +1,41 @@
+use crop::Rope;
+use std::collections::HashMap;
+
+use crate::undo::Changeset;
+
+/// The in-memory state of the buffer being edited: its text, the file
+/// it's associated with, where the cursor sits, the named marks set by
+/// the user, and the undo/redo history.
+pub struct BedState {
+    pub content: Rope,
+    pub filename: String,
+    pub current_line: usize,
+    pub marks: HashMap<char, usize>,
+    pub undo: Vec<Changeset>,
+    pub redo: Vec<Changeset>,
+}
+
+impl BedState {
+    pub fn new(content: Rope, filename: String) -> Self {
+        let current_line = content.line_len();
+        BedState {
+            content,
+            filename,
+            current_line,
+            marks: HashMap::new(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Records a changeset as the most recent action, making it available
+    /// to `u`, and discards the redo history (a fresh edit invalidates any
+    /// previously undone redos). No-op for a changeset that touched
+    /// nothing.
+    pub fn record(&mut self, changeset: Changeset) {
+        if !changeset.is_empty() {
+            self.redo.clear();
+            self.undo.push(changeset);
+        }
+    }
+}
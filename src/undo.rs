@@ -0,0 +1,53 @@
+use crop::Rope;
+
+/// A single primitive edit to the buffer, recorded so it can later be
+/// reversed.
+#[derive(Debug, Clone)]
+pub enum Edit {
+    Insert { byte: usize, text: String },
+    Delete { byte: usize, text: String },
+}
+
+/// A group of primitive edits that make up one user-visible action (e.g.
+/// a whole `c` replacement or a multi-line `s///`), undone or redone
+/// together.
+#[derive(Debug, Clone, Default)]
+pub struct Changeset {
+    edits: Vec<Edit>,
+}
+
+impl Changeset {
+    pub fn new() -> Self {
+        Changeset::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    pub fn push(&mut self, edit: Edit) {
+        self.edits.push(edit);
+    }
+
+    /// Re-applies the edits that made up this changeset, in the order
+    /// they originally happened.
+    pub fn apply(&self, content: &mut Rope) {
+        for edit in &self.edits {
+            match edit {
+                Edit::Insert { byte, text } => content.insert(*byte, text),
+                Edit::Delete { byte, text } => content.delete(*byte..(*byte + text.len())),
+            }
+        }
+    }
+
+    /// Undoes this changeset by applying the inverse of each edit, last
+    /// one first.
+    pub fn apply_inverse(&self, content: &mut Rope) {
+        for edit in self.edits.iter().rev() {
+            match edit {
+                Edit::Insert { byte, text } => content.delete(*byte..(*byte + text.len())),
+                Edit::Delete { byte, text } => content.insert(*byte, text),
+            }
+        }
+    }
+}